@@ -0,0 +1,298 @@
+//! An in-memory ring buffer of recently logged records, queryable
+//! independently of the Prometheus scrape.
+
+use crate::macros::emit_via_to_string;
+use slog::Level;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+/// A single record retained by the memory buffer.
+#[derive(Debug, Clone)]
+pub struct StoredRecord {
+    /// When the record was logged.
+    pub timestamp: SystemTime,
+    /// The record's log level.
+    pub level: Level,
+    /// The record's tag, if non-empty, otherwise its module path.
+    pub module: String,
+    /// The rendered log message.
+    pub message: String,
+    /// The record's structured key-value pairs, in emission order.
+    pub kv: Vec<(String, String)>,
+}
+
+/// A filter for [`MonitoringDrain::query`](crate::MonitoringDrain::query).
+///
+/// All fields default to "don't filter on this"; an empty `RecordFilter`
+/// matches every buffered record.
+#[derive(Debug, Default)]
+pub struct RecordFilter {
+    /// Only include records at least as severe as this level.
+    pub min_level: Option<Level>,
+    /// Only include records whose `module` contains this substring.
+    pub module: Option<String>,
+    /// Only include records whose message matches this regex.
+    pub message: Option<regex::Regex>,
+    /// Only include records logged at or after this time.
+    pub not_before: Option<SystemTime>,
+    /// Return at most this many records.
+    pub limit: Option<usize>,
+}
+
+impl RecordFilter {
+    fn matches(&self, record: &StoredRecord) -> bool {
+        if let Some(min_level) = self.min_level {
+            if !record.level.is_at_least(min_level) {
+                return false;
+            }
+        }
+        if let Some(module) = &self.module {
+            if !record.module.contains(module.as_str()) {
+                return false;
+            }
+        }
+        if let Some(message) = &self.message {
+            if !message.is_match(&record.message) {
+                return false;
+            }
+        }
+        if let Some(not_before) = self.not_before {
+            if record.timestamp < not_before {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Bounded, time-pruned store of recently logged records.
+pub(crate) struct MemoryBuffer {
+    capacity: usize,
+    keep: Duration,
+    records: Mutex<VecDeque<StoredRecord>>,
+}
+
+impl MemoryBuffer {
+    pub(crate) fn new(capacity: usize, keep: Duration) -> Self {
+        Self {
+            capacity,
+            keep,
+            records: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    /// Store a record, then drop anything past `capacity` or older than
+    /// `keep` relative to the record just inserted.
+    pub(crate) fn insert(&self, record: StoredRecord) {
+        let now = record.timestamp;
+        let mut records = self.records.lock().expect("lock poisoned");
+        records.push_back(record);
+
+        while records.len() > self.capacity {
+            records.pop_front();
+        }
+
+        if let Some(cutoff) = now.checked_sub(self.keep) {
+            while matches!(records.front(), Some(oldest) if oldest.timestamp < cutoff) {
+                records.pop_front();
+            }
+        }
+    }
+
+    /// Matching records, newest-first.
+    pub(crate) fn query(&self, filter: &RecordFilter) -> Vec<StoredRecord> {
+        let records = self.records.lock().expect("lock poisoned");
+        records
+            .iter()
+            .rev()
+            .filter(|record| filter.matches(record))
+            .take(filter.limit.unwrap_or(usize::MAX))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Collects every structured key-value pair on a record into an ordered
+/// `Vec`, for storage in a [`StoredRecord`].
+pub(crate) struct KvRenderer(pub(crate) Vec<(String, String)>);
+
+impl KvRenderer {
+    pub(crate) fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    fn push(&mut self, key: slog::Key, value: String) {
+        self.0.push((key.to_string(), value));
+    }
+}
+
+impl slog::Serializer for KvRenderer {
+    fn emit_str(&mut self, key: slog::Key, val: &str) -> slog::Result {
+        self.push(key, val.to_string());
+        Ok(())
+    }
+
+    fn emit_arguments(&mut self, key: slog::Key, val: &std::fmt::Arguments) -> slog::Result {
+        self.push(key, val.to_string());
+        Ok(())
+    }
+
+    emit_via_to_string! {
+        emit_usize: usize,
+        emit_isize: isize,
+        emit_u8: u8,
+        emit_i8: i8,
+        emit_u16: u16,
+        emit_i16: i16,
+        emit_u32: u32,
+        emit_i32: i32,
+        emit_u64: u64,
+        emit_i64: i64,
+        emit_f32: f32,
+        emit_f64: f64,
+        emit_bool: bool,
+        emit_char: char,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(timestamp: SystemTime, level: Level, module: &str, message: &str) -> StoredRecord {
+        StoredRecord {
+            timestamp,
+            level,
+            module: module.to_string(),
+            message: message.to_string(),
+            kv: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn query_returns_newest_first() {
+        let buffer = MemoryBuffer::new(10, Duration::from_secs(3600));
+        let t0 = SystemTime::now();
+
+        buffer.insert(record(t0, Level::Info, "a", "first"));
+        buffer.insert(record(t0 + Duration::from_secs(1), Level::Info, "a", "second"));
+        buffer.insert(record(t0 + Duration::from_secs(2), Level::Info, "a", "third"));
+
+        let results = buffer.query(&RecordFilter::default());
+        let messages: Vec<&str> = results.iter().map(|r| r.message.as_str()).collect();
+        assert_eq!(messages, vec!["third", "second", "first"]);
+    }
+
+    #[test]
+    fn query_filters_by_min_level() {
+        let buffer = MemoryBuffer::new(10, Duration::from_secs(3600));
+        let now = SystemTime::now();
+
+        buffer.insert(record(now, Level::Debug, "a", "debug"));
+        buffer.insert(record(now, Level::Error, "a", "error"));
+
+        let results = buffer.query(&RecordFilter {
+            min_level: Some(Level::Warning),
+            ..Default::default()
+        });
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].message, "error");
+    }
+
+    #[test]
+    fn query_filters_by_module_substring() {
+        let buffer = MemoryBuffer::new(10, Duration::from_secs(3600));
+        let now = SystemTime::now();
+
+        buffer.insert(record(now, Level::Info, "auth::login", "a"));
+        buffer.insert(record(now, Level::Info, "billing::invoice", "b"));
+
+        let results = buffer.query(&RecordFilter {
+            module: Some("auth".to_string()),
+            ..Default::default()
+        });
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].module, "auth::login");
+    }
+
+    #[test]
+    fn query_filters_by_message_regex() {
+        let buffer = MemoryBuffer::new(10, Duration::from_secs(3600));
+        let now = SystemTime::now();
+
+        buffer.insert(record(now, Level::Info, "a", "user 42 logged in"));
+        buffer.insert(record(now, Level::Info, "a", "request failed"));
+
+        let results = buffer.query(&RecordFilter {
+            message: Some(regex::Regex::new(r"\d+").unwrap()),
+            ..Default::default()
+        });
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].message, "user 42 logged in");
+    }
+
+    #[test]
+    fn query_filters_by_not_before() {
+        let buffer = MemoryBuffer::new(10, Duration::from_secs(3600));
+        let t0 = SystemTime::now();
+
+        buffer.insert(record(t0, Level::Info, "a", "old"));
+        buffer.insert(record(t0 + Duration::from_secs(10), Level::Info, "a", "new"));
+
+        let results = buffer.query(&RecordFilter {
+            not_before: Some(t0 + Duration::from_secs(5)),
+            ..Default::default()
+        });
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].message, "new");
+    }
+
+    #[test]
+    fn query_respects_limit() {
+        let buffer = MemoryBuffer::new(10, Duration::from_secs(3600));
+        let now = SystemTime::now();
+
+        buffer.insert(record(now, Level::Info, "a", "first"));
+        buffer.insert(record(now, Level::Info, "a", "second"));
+        buffer.insert(record(now, Level::Info, "a", "third"));
+
+        let results = buffer.query(&RecordFilter {
+            limit: Some(2),
+            ..Default::default()
+        });
+
+        assert_eq!(results.len(), 2);
+    }
+
+    #[test]
+    fn insert_prunes_past_capacity() {
+        let buffer = MemoryBuffer::new(2, Duration::from_secs(3600));
+        let now = SystemTime::now();
+
+        buffer.insert(record(now, Level::Info, "a", "first"));
+        buffer.insert(record(now, Level::Info, "a", "second"));
+        buffer.insert(record(now, Level::Info, "a", "third"));
+
+        let results = buffer.query(&RecordFilter::default());
+        let messages: Vec<&str> = results.iter().map(|r| r.message.as_str()).collect();
+        assert_eq!(messages, vec!["third", "second"]);
+    }
+
+    #[test]
+    fn insert_prunes_past_keep_duration() {
+        let buffer = MemoryBuffer::new(10, Duration::from_secs(5));
+        let t0 = SystemTime::now();
+
+        buffer.insert(record(t0, Level::Info, "a", "old"));
+        buffer.insert(record(t0 + Duration::from_secs(10), Level::Info, "a", "new"));
+
+        let results = buffer.query(&RecordFilter::default());
+        let messages: Vec<&str> = results.iter().map(|r| r.message.as_str()).collect();
+        assert_eq!(messages, vec!["new"]);
+    }
+}
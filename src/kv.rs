@@ -0,0 +1,61 @@
+//! A [`slog::Serializer`] that collects a configured set of structured
+//! key-value pairs into owned strings, for promotion to Prometheus labels.
+
+use crate::macros::emit_via_to_string;
+use std::collections::{HashMap, HashSet};
+
+/// Collects the configured `keys` out of a record's key-value pairs,
+/// stringifying each value it keeps (numbers via `to_string`, strings
+/// directly) and discarding anything not in the set.
+pub(crate) struct KvCollector<'a> {
+    keys: &'a HashSet<String>,
+    values: HashMap<String, String>,
+}
+
+impl<'a> KvCollector<'a> {
+    pub(crate) fn new(keys: &'a HashSet<String>) -> Self {
+        Self {
+            keys,
+            values: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn into_values(self) -> HashMap<String, String> {
+        self.values
+    }
+
+    fn push(&mut self, key: slog::Key, value: String) {
+        if self.keys.contains(key as &str) {
+            self.values.insert(key.to_string(), value);
+        }
+    }
+}
+
+impl<'a> slog::Serializer for KvCollector<'a> {
+    fn emit_str(&mut self, key: slog::Key, val: &str) -> slog::Result {
+        self.push(key, val.to_string());
+        Ok(())
+    }
+
+    fn emit_arguments(&mut self, key: slog::Key, val: &std::fmt::Arguments) -> slog::Result {
+        self.push(key, val.to_string());
+        Ok(())
+    }
+
+    emit_via_to_string! {
+        emit_usize: usize,
+        emit_isize: isize,
+        emit_u8: u8,
+        emit_i8: i8,
+        emit_u16: u16,
+        emit_i16: i16,
+        emit_u32: u32,
+        emit_i32: i32,
+        emit_u64: u64,
+        emit_i64: i64,
+        emit_f32: f32,
+        emit_f64: f64,
+        emit_bool: bool,
+        emit_char: char,
+    }
+}
@@ -1,38 +1,49 @@
 //! Record prometheus metrics for messages logged.
-//! 
+//!
 //! Use the MonitoringDrainBuilder to configure a drain, putting it in an
 //! appropriate spot in the slog drain stack.
-//! 
+//!
 //! ```rust
 //! use slog::{info, Drain};
-//! 
+//!
 //! let decorator = slog_term::TermDecorator::new().build();
 //! let drain = slog_term::FullFormat::new(decorator).build().fuse();
 //! let drain = slog_async::Async::new(drain).build();
-//! 
+//!
 //! let drain = slog_prometheus::MonitoringDrainBuilder::new(drain)
 //!     .build()
 //!     .expect("Failed configuring setting up prometheus")
 //!     .fuse();
 //! let drain = slog::LevelFilter::new(drain, slog::Level::Info).fuse();
-//! 
+//!
 //! let logger = slog::Logger::root(drain, slog::o!());
-//! 
+//!
 //! info!(logger, "Finished setting up!");
-//! 
+//!
 //! ```
 #![warn(missing_docs)]
 use prometheus::{
     core::{AtomicF64, GenericCounter},
-    Counter, CounterVec, Opts, Registry,
+    Counter, CounterVec, GaugeVec, Histogram, HistogramOpts, Opts, Registry,
 };
-use slog::{Drain, Level, LOG_LEVEL_NAMES};
+use slog::{Drain, Level, LOG_LEVEL_NAMES, KV};
+use std::collections::{HashMap, HashSet};
+use std::panic::AssertUnwindSafe;
 use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use thiserror::Error;
 
+mod buffer;
+mod kv;
+mod macros;
+use buffer::MemoryBuffer;
+pub use buffer::{RecordFilter, StoredRecord};
+use kv::KvCollector;
+
 /// During build, prometheus might return an error, which requires this crate
 /// to return an error.
-/// 
+///
 /// Currently, this is the only error returned.
 #[derive(Error, Debug)]
 pub enum MonitoringDrainError {
@@ -41,15 +52,62 @@ pub enum MonitoringDrainError {
     Prometheus(#[from] prometheus::Error),
 }
 
+/// `log_events`, in either of its two configurations.
+///
+/// Without `module_field`/`label_from_kv`, the level/level_no combinations
+/// are known up front, so `Fixed` pre-materializes one counter handle per
+/// level (the hot path: one array index + atomic increment). With either
+/// dynamic label enabled, label values aren't known up front, so `Dynamic`
+/// falls back to `with_label_values` lookups at log time.
+enum LogEventsCounter {
+    Fixed([GenericCounter<AtomicF64>; LEVEL_COUNT]),
+    /// `CounterVec`'s label map is behind a `parking_lot::RwLock`, which
+    /// isn't `RefUnwindSafe`; `slog::Logger::root` requires the whole drain
+    /// to be, so this is wrapped to assert that a poisoned label map here
+    /// (we never panic while holding it) isn't a soundness concern.
+    Dynamic(AssertUnwindSafe<CounterVec>),
+}
+
 /// The main monitoring struct, implementing slog::Drain
 pub struct MonitoringDrain<D: Drain> {
     core: D,
-    log_events: [GenericCounter<AtomicF64>; LEVEL_COUNT],
+    log_events: LogEventsCounter,
     log_events_failed: GenericCounter<AtomicF64>,
+    /// Allow-list of module names to keep in the `module` label; modules not
+    /// in the list are folded into `"other"`. `None` means the label isn't
+    /// populated at all, `Some(None)` means it's populated without a cap.
+    module_allow_list: Option<Option<HashSet<String>>>,
+    /// Structured kv keys promoted to `log_events` labels, in label order.
+    kv_labels: Vec<String>,
+    /// Same keys as `kv_labels`, for cheap membership checks in the
+    /// per-record serializer.
+    kv_label_set: HashSet<String>,
+    /// Caps the number of distinct values any one `kv_labels` key may take;
+    /// once a key hits the cap, further unseen values are recorded as `""`
+    /// instead of growing the series further.
+    kv_cardinality_limit: Option<usize>,
+    /// Distinct values observed so far per kv label, only tracked when
+    /// `kv_cardinality_limit` is set.
+    kv_seen_values: Mutex<HashMap<String, HashSet<String>>>,
+    /// Times how long `core.log` takes, when enabled via
+    /// [`MonitoringDrainBuilder::with_latency_histogram`].
+    latency_histogram: Option<Histogram>,
+    /// Recent records, when enabled via
+    /// [`MonitoringDrainBuilder::with_memory_buffer`].
+    memory_buffer: Option<MemoryBuffer>,
+    /// `log_event_last_timestamp_seconds`, keyed by level; disabled via
+    /// [`MonitoringDrainBuilder::without_last_timestamp_gauge`]. Wrapped for
+    /// the same `RefUnwindSafe` reason as `log_events`, above.
+    last_timestamp_gauge: Option<AssertUnwindSafe<GaugeVec>>,
+    /// `log_events_in_flight`, keyed by level; disabled via
+    /// [`MonitoringDrainBuilder::without_in_flight_gauge`]. Wrapped for the
+    /// same `RefUnwindSafe` reason as `log_events`, above.
+    in_flight_gauge: Option<AssertUnwindSafe<GaugeVec>>,
 }
 
 const LEVEL: &str = "level";
 const LEVEL_NO: &str = "level_no";
+const MODULE_OTHER: &str = "other";
 const LEVEL_COUNT: usize = 6;
 
 /// Helper struct to build the MonitoringDrain conveniently
@@ -58,6 +116,14 @@ pub struct MonitoringDrainBuilder<'a, 'b, D: Drain> {
     registry: &'b Registry,
     level_field: &'a str,
     level_no_field: &'a str,
+    module_field: Option<&'a str>,
+    module_allow_list: Option<Vec<&'a str>>,
+    kv_labels: Vec<&'a str>,
+    kv_cardinality_limit: Option<usize>,
+    latency_buckets: Option<Vec<f64>>,
+    memory_buffer: Option<(usize, Duration)>,
+    last_timestamp_gauge_enabled: bool,
+    in_flight_gauge_enabled: bool,
 }
 
 impl<'a, 'b, D: Drain> MonitoringDrainBuilder<'a, 'b, D> {
@@ -68,6 +134,14 @@ impl<'a, 'b, D: Drain> MonitoringDrainBuilder<'a, 'b, D> {
             registry: prometheus::default_registry(),
             level_field: LEVEL,
             level_no_field: LEVEL_NO,
+            module_field: None,
+            module_allow_list: None,
+            kv_labels: Vec::new(),
+            kv_cardinality_limit: None,
+            latency_buckets: None,
+            memory_buffer: None,
+            last_timestamp_gauge_enabled: true,
+            in_flight_gauge_enabled: true,
         }
     }
 
@@ -89,38 +163,266 @@ impl<'a, 'b, D: Drain> MonitoringDrainBuilder<'a, 'b, D> {
         self
     }
 
+    /// Opt in to a `module` label on `log_events`, named `module_field`,
+    /// populated from the record's module path (or its tag, when the tag is
+    /// non-empty).
+    ///
+    /// Because module names aren't known up front, enabling this switches
+    /// `log_events` from pre-materialized per-level counters to
+    /// `with_label_values` lookups performed at log time. Without
+    /// [`Self::module_allow_list`] this can grow the series count
+    /// unboundedly as new modules start logging, so pass one whenever the
+    /// set of modules isn't already bounded.
+    pub fn module_field(mut self, module_field: &'a str) -> Self {
+        self.module_field = Some(module_field);
+        self
+    }
+
+    /// Cap the `module` label (see [`Self::module_field`]) to the given
+    /// module names; any module not in the list is recorded under an
+    /// `"other"` bucket instead of creating its own series.
+    pub fn module_allow_list(mut self, modules: &[&'a str]) -> Self {
+        self.module_allow_list = Some(modules.to_vec());
+        self
+    }
+
+    /// Promote selected structured key-value fields on log records to
+    /// `log_events` labels, named the same as the given keys.
+    ///
+    /// slog records carry arbitrary structured key-value pairs; any of the
+    /// given `keys` present on a record (checked against both the record's
+    /// own kv pairs and the logger's inherited ones) is stringified and used
+    /// as that label's value, defaulting to `""` when the key is absent.
+    /// Like [`Self::module_field`], this switches `log_events` off
+    /// pre-materialized counters.
+    ///
+    /// **Cardinality risk**: every distinct combination of values becomes
+    /// its own series. Use [`Self::kv_cardinality_limit`] to bound how many
+    /// distinct values any one key may take.
+    pub fn label_from_kv(mut self, keys: &[&'a str]) -> Self {
+        self.kv_labels = keys.to_vec();
+        self
+    }
+
+    /// Cap the number of distinct values any single [`Self::label_from_kv`]
+    /// key may take; once a key hits the cap, further unseen values are
+    /// recorded as `""` instead of growing the series further.
+    pub fn kv_cardinality_limit(mut self, limit: usize) -> Self {
+        self.kv_cardinality_limit = Some(limit);
+        self
+    }
+
+    /// Opt in to a `log_event_duration_seconds` histogram timing how long
+    /// the wrapped drain's `log` call takes, so an async or network drain
+    /// downstream that starts blocking the logging path shows up here
+    /// rather than as an unexplained slowdown.
+    ///
+    /// Pass `prometheus::DEFAULT_BUCKETS.to_vec()` for Prometheus' standard
+    /// buckets, or a custom set sized to the expected latency range.
+    pub fn with_latency_histogram(mut self, buckets: Vec<f64>) -> Self {
+        self.latency_buckets = Some(buckets);
+        self
+    }
+
+    /// Opt in to retaining the most recent `capacity` log records (pruning
+    /// anything older than `keep`) so they can be pulled back out via
+    /// [`MonitoringDrain::query`] right after a metric spike, without a
+    /// separate log aggregator.
+    pub fn with_memory_buffer(mut self, capacity: usize, keep: Duration) -> Self {
+        self.memory_buffer = Some((capacity, keep));
+        self
+    }
+
+    /// Disable the `log_event_last_timestamp_seconds` gauge (keyed by
+    /// level, set to the current unix time on every log event). Enabled by
+    /// default; lets alerting rules fire on e.g. "no error logs in N
+    /// minutes means the error pipeline died".
+    pub fn without_last_timestamp_gauge(mut self) -> Self {
+        self.last_timestamp_gauge_enabled = false;
+        self
+    }
+
+    /// Disable the `log_events_in_flight` gauge (keyed by level,
+    /// incremented before and decremented after the wrapped drain's `log`
+    /// call). Enabled by default; lets alerting rules fire on logging
+    /// backpressure when a downstream drain stalls.
+    pub fn without_in_flight_gauge(mut self) -> Self {
+        self.in_flight_gauge_enabled = false;
+        self
+    }
+
     /// Build the monitoring drain
     pub fn build(self) -> Result<MonitoringDrain<D>, MonitoringDrainError> {
-        let opts = Opts::new("log_events", "Log events emitted by this logger.");
-        let metrics_builder = CounterVec::new(opts, &[self.level_field, self.level_no_field])?;
-        self.registry.register(Box::new(metrics_builder.clone()))?;
-
-        let mut log_events: Vec<GenericCounter<AtomicF64>> = Vec::new();
-        for &level_str in LOG_LEVEL_NAMES[1..].iter() {
-            let level =
-                Level::from_str(level_str).expect("Iterating directly over the sourced array");
-            log_events.push(
-                metrics_builder.with_label_values(&[level.as_str(), &level.as_usize().to_string()]),
-            );
+        let mut label_names = vec![self.level_field, self.level_no_field];
+        if let Some(module_field) = self.module_field {
+            label_names.push(module_field);
         }
+        label_names.extend(self.kv_labels.iter().copied());
 
-        let level_array: [GenericCounter<AtomicF64>; LEVEL_COUNT] = log_events
-            .try_into()
-            .expect("Source is built directly via iteration over the source array");
+        let opts = Opts::new("log_events", "Log events emitted by this logger.");
+        let log_events_vec = CounterVec::new(opts, &label_names)?;
+        self.registry.register(Box::new(log_events_vec.clone()))?;
+
+        let log_events = if self.module_field.is_none() && self.kv_labels.is_empty() {
+            // Neither dynamic label is in use, so the level/level_no
+            // combinations are known up front: pre-materialize one counter
+            // handle per level, so logging stays a plain array index plus
+            // atomic increment instead of a `with_label_values` lookup.
+            let mut counters: Vec<GenericCounter<AtomicF64>> = Vec::new();
+            for &level_str in LOG_LEVEL_NAMES[1..].iter() {
+                let level = Level::from_str(level_str)
+                    .expect("Iterating directly over the sourced array");
+                counters.push(
+                    log_events_vec
+                        .with_label_values(&[level.as_str(), &level.as_usize().to_string()]),
+                );
+            }
+            let counters: [GenericCounter<AtomicF64>; LEVEL_COUNT] = counters
+                .try_into()
+                .expect("Source is built directly via iteration over the source array");
+            LogEventsCounter::Fixed(counters)
+        } else {
+            LogEventsCounter::Dynamic(AssertUnwindSafe(log_events_vec))
+        };
 
         let opts = Opts::new("log_events_failed", "Log events which failed to be logged.");
         let log_events_failed = Counter::with_opts(opts)?;
         self.registry
             .register(Box::new(log_events_failed.clone()))?;
 
+        let module_allow_list = self.module_field.map(|_| {
+            self.module_allow_list
+                .map(|modules| modules.into_iter().map(String::from).collect())
+        });
+
+        let kv_label_set = self.kv_labels.iter().map(|&k| k.to_string()).collect();
+        let kv_labels = self.kv_labels.into_iter().map(String::from).collect();
+
+        let latency_histogram = self
+            .latency_buckets
+            .map(|buckets| -> Result<Histogram, MonitoringDrainError> {
+                let opts = HistogramOpts::new(
+                    "log_event_duration_seconds",
+                    "Time spent in the wrapped drain's log call.",
+                )
+                .buckets(buckets);
+                let histogram = Histogram::with_opts(opts)?;
+                self.registry.register(Box::new(histogram.clone()))?;
+                Ok(histogram)
+            })
+            .transpose()?;
+
+        let memory_buffer = self
+            .memory_buffer
+            .map(|(capacity, keep)| MemoryBuffer::new(capacity, keep));
+
+        let last_timestamp_gauge = self
+            .last_timestamp_gauge_enabled
+            .then(|| -> Result<GaugeVec, MonitoringDrainError> {
+                let opts = Opts::new(
+                    "log_event_last_timestamp_seconds",
+                    "Unix timestamp of the last log event at this level.",
+                );
+                let gauge = GaugeVec::new(opts, &[self.level_field])?;
+                self.registry.register(Box::new(gauge.clone()))?;
+                Ok(gauge)
+            })
+            .transpose()?;
+
+        let in_flight_gauge = self
+            .in_flight_gauge_enabled
+            .then(|| -> Result<GaugeVec, MonitoringDrainError> {
+                let opts = Opts::new(
+                    "log_events_in_flight",
+                    "Log events currently being handled by the wrapped drain.",
+                );
+                let gauge = GaugeVec::new(opts, &[self.level_field])?;
+                self.registry.register(Box::new(gauge.clone()))?;
+                Ok(gauge)
+            })
+            .transpose()?;
+
         Ok(MonitoringDrain {
             core: self.core,
-            log_events: level_array,
+            log_events,
             log_events_failed,
+            module_allow_list,
+            kv_labels,
+            kv_label_set,
+            kv_cardinality_limit: self.kv_cardinality_limit,
+            kv_seen_values: Mutex::new(HashMap::new()),
+            latency_histogram,
+            memory_buffer,
+            last_timestamp_gauge: last_timestamp_gauge.map(AssertUnwindSafe),
+            in_flight_gauge: in_flight_gauge.map(AssertUnwindSafe),
         })
     }
 }
 
+/// The record's tag, if non-empty, otherwise its module path.
+fn record_module<'r>(record: &'r slog::Record) -> &'r str {
+    if !record.tag().is_empty() {
+        record.tag()
+    } else {
+        record.module()
+    }
+}
+
+impl<D: Drain> MonitoringDrain<D> {
+    /// Records matching `filter`, newest-first. Always empty unless
+    /// [`MonitoringDrainBuilder::with_memory_buffer`] was used to build
+    /// this drain.
+    pub fn query(&self, filter: &RecordFilter) -> Vec<StoredRecord> {
+        self.memory_buffer
+            .as_ref()
+            .map(|buffer| buffer.query(filter))
+            .unwrap_or_default()
+    }
+
+    /// Stringified values for `kv_labels`, in label order, defaulting
+    /// missing keys to `""` and capping per-key cardinality when configured.
+    fn kv_label_values(&self, record: &slog::Record, values: &slog::OwnedKVList) -> Vec<String> {
+        if self.kv_labels.is_empty() {
+            return Vec::new();
+        }
+
+        let mut collector = KvCollector::new(&self.kv_label_set);
+        // Record-specific kv pairs take precedence over the logger's
+        // inherited ones, so serialize those last.
+        let _ = values.serialize(record, &mut collector);
+        let _ = record.kv().serialize(record, &mut collector);
+        let mut collected = collector.into_values();
+
+        self.kv_labels
+            .iter()
+            .map(|key| {
+                let value = collected.remove(key).unwrap_or_default();
+                self.cap_kv_cardinality(key, value)
+            })
+            .collect()
+    }
+
+    /// Applies `kv_cardinality_limit`: once a key has seen that many
+    /// distinct values, any further unseen value is reported as `""`
+    /// instead of creating a new series.
+    fn cap_kv_cardinality(&self, key: &str, value: String) -> String {
+        let Some(limit) = self.kv_cardinality_limit else {
+            return value;
+        };
+
+        let mut seen_values = self.kv_seen_values.lock().expect("lock poisoned");
+        let seen = seen_values.entry(key.to_string()).or_default();
+        if seen.contains(&value) {
+            value
+        } else if seen.len() < limit {
+            seen.insert(value.clone());
+            value
+        } else {
+            String::new()
+        }
+    }
+}
+
 impl<D: Drain> Drain for MonitoringDrain<D> {
     type Ok = D::Ok;
 
@@ -132,13 +434,69 @@ impl<D: Drain> Drain for MonitoringDrain<D> {
         values: &slog::OwnedKVList,
     ) -> std::result::Result<Self::Ok, Self::Err> {
         let level = record.level();
-        let level_no = level.as_usize();
 
-        let metric = &self.log_events[level_no - 1];
-        metric.inc();
+        match &self.log_events {
+            LogEventsCounter::Fixed(counters) => {
+                counters[level.as_usize() - 1].inc();
+            }
+            LogEventsCounter::Dynamic(log_events) => {
+                let level_no_str = level.as_usize().to_string();
+                let mut label_values = vec![level.as_str().to_string(), level_no_str];
+
+                if let Some(allow_list) = &self.module_allow_list {
+                    let module = record_module(record);
+                    let module = match allow_list {
+                        Some(allow_list) if !allow_list.contains(module) => MODULE_OTHER,
+                        _ => module,
+                    };
+                    label_values.push(module.to_string());
+                }
+
+                label_values.extend(self.kv_label_values(record, values));
+
+                let label_refs: Vec<&str> = label_values.iter().map(String::as_str).collect();
+                log_events.with_label_values(&label_refs).inc();
+            }
+        }
+
+        if let Some(buffer) = &self.memory_buffer {
+            let mut renderer = buffer::KvRenderer::new();
+            let _ = values.serialize(record, &mut renderer);
+            let _ = record.kv().serialize(record, &mut renderer);
+
+            buffer.insert(StoredRecord {
+                timestamp: SystemTime::now(),
+                level,
+                module: record_module(record).to_string(),
+                message: record.msg().to_string(),
+                kv: renderer.0,
+            });
+        }
+
+        if let Some(gauge) = &self.last_timestamp_gauge {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs_f64();
+            gauge.with_label_values(&[level.as_str()]).set(now);
+        }
+
+        if let Some(gauge) = &self.in_flight_gauge {
+            gauge.with_label_values(&[level.as_str()]).inc();
+        }
+
+        let start = self.latency_histogram.as_ref().map(|_| Instant::now());
 
         let res = self.core.log(record, values);
 
+        if let (Some(histogram), Some(start)) = (&self.latency_histogram, start) {
+            histogram.observe(start.elapsed().as_secs_f64());
+        }
+
+        if let Some(gauge) = &self.in_flight_gauge {
+            gauge.with_label_values(&[level.as_str()]).dec();
+        }
+
         if res.is_err() {
             self.log_events_failed.inc();
         }
@@ -152,9 +510,9 @@ mod tests {
     use std::sync::atomic::{AtomicUsize, Ordering};
 
     use prometheus::Registry;
-    use slog::{info, o, Drain, Record, LOG_LEVEL_NAMES};
+    use slog::{info, o, Drain, Record};
 
-    use crate::{MonitoringDrainBuilder, LEVEL_COUNT};
+    use crate::MonitoringDrainBuilder;
 
     struct StoringDrain<'a> {
         records: &'a AtomicUsize,
@@ -262,8 +620,242 @@ mod tests {
     }
 
     #[test]
-    fn check_same_size() {
-        // Ensure these match, otherwise retrieving the level doesn't work
-        assert_eq!(LEVEL_COUNT, LOG_LEVEL_NAMES.len() - 1);
+    fn in_flight_gauge_returns_to_zero_after_success() {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let drain = StoringDrain { records: &COUNTER };
+
+        let registry = Registry::new();
+        let drain = MonitoringDrainBuilder::new(drain)
+            .registry(&registry)
+            .build()
+            .expect("No error during default drain creation")
+            .fuse();
+
+        let _log = slog::Logger::root(drain, o!());
+        info!(_log, "A info message");
+
+        let metrics = registry.gather();
+        for m in metrics {
+            if let "log_events_in_flight" = m.get_name() {
+                assert_eq!(
+                    0 as f64,
+                    m.get_metric().get(0).unwrap().get_gauge().get_value()
+                );
+            }
+            if let "log_event_last_timestamp_seconds" = m.get_name() {
+                assert!(m.get_metric().get(0).unwrap().get_gauge().get_value() > 0 as f64);
+            }
+        }
+    }
+
+    #[test]
+    fn in_flight_gauge_returns_to_zero_after_failure() {
+        let drain = FailDrain {};
+
+        let registry = Registry::new();
+        let drain = MonitoringDrainBuilder::new(drain)
+            .registry(&registry)
+            .build()
+            .expect("No error during default drain creation")
+            .ignore_res();
+
+        let _log = slog::Logger::root(drain, o!());
+        info!(_log, "A info message");
+
+        let metrics = registry.gather();
+        for m in metrics {
+            if let "log_events_in_flight" = m.get_name() {
+                assert_eq!(
+                    0 as f64,
+                    m.get_metric().get(0).unwrap().get_gauge().get_value()
+                );
+            }
+            if let "log_event_last_timestamp_seconds" = m.get_name() {
+                assert!(m.get_metric().get(0).unwrap().get_gauge().get_value() > 0 as f64);
+            }
+        }
+    }
+
+    #[test]
+    fn log_event_duration_is_observed() {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let drain = StoringDrain { records: &COUNTER };
+
+        let registry = Registry::new();
+        let drain = MonitoringDrainBuilder::new(drain)
+            .registry(&registry)
+            .with_latency_histogram(prometheus::DEFAULT_BUCKETS.to_vec())
+            .build()
+            .expect("No error during default drain creation")
+            .fuse();
+
+        let _log = slog::Logger::root(drain, o!());
+        info!(_log, "A info message");
+
+        let metrics = registry.gather();
+
+        for m in metrics {
+            if let "log_event_duration_seconds" = m.get_name() {
+                let histogram = m.get_metric().get(0).unwrap().get_histogram();
+                assert_eq!(1, histogram.get_sample_count());
+            }
+        }
+    }
+
+    #[test]
+    fn log_with_dynamic_labels_via_logger_root() {
+        // `Logger::root` requires the drain to be `RefUnwindSafe`; with
+        // `module_field`/`label_from_kv` enabled, `log_events` is a live
+        // `CounterVec` rather than pre-materialized counters, so this
+        // exercises that path specifically.
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let drain = StoringDrain { records: &COUNTER };
+
+        let registry = Registry::new();
+        let drain = MonitoringDrainBuilder::new(drain)
+            .registry(&registry)
+            .module_field("module")
+            .label_from_kv(&["component"])
+            .build()
+            .expect("No error during default drain creation")
+            .fuse();
+
+        let logger = slog::Logger::root(drain, o!());
+        info!(logger, "A info message"; "component" => "auth");
+
+        assert_eq!(COUNTER.load(Ordering::Relaxed), 1);
+
+        let metrics = registry.gather();
+        for m in metrics {
+            if let "log_events" = m.get_name() {
+                let found = m.get_metric().iter().any(|metric| {
+                    metric
+                        .get_label()
+                        .iter()
+                        .any(|l| l.get_name() == "component" && l.get_value() == "auth")
+                });
+                assert!(found, "expected a log_events series with component=auth");
+            }
+        }
+    }
+
+    #[test]
+    fn label_from_kv_prefers_record_kv_over_logger_kv() {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let drain = StoringDrain { records: &COUNTER };
+
+        let registry = Registry::new();
+        let drain = MonitoringDrainBuilder::new(drain)
+            .registry(&registry)
+            .label_from_kv(&["component"])
+            .build()
+            .expect("No error during default drain creation")
+            .fuse();
+
+        // The logger carries "component" => "inherited"; the per-record kv
+        // should win.
+        let logger = slog::Logger::root(drain, o!("component" => "inherited"));
+        info!(logger, "A info message"; "component" => "auth");
+
+        let metrics = registry.gather();
+        for m in metrics {
+            if let "log_events" = m.get_name() {
+                let found = m.get_metric().iter().any(|metric| {
+                    metric
+                        .get_label()
+                        .iter()
+                        .any(|l| l.get_name() == "component" && l.get_value() == "auth")
+                });
+                assert!(found, "expected record kv to win over logger-inherited kv");
+            }
+        }
+    }
+
+    #[test]
+    fn label_from_kv_defaults_missing_key_to_empty_string() {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let drain = StoringDrain { records: &COUNTER };
+
+        let registry = Registry::new();
+        let drain = MonitoringDrainBuilder::new(drain)
+            .registry(&registry)
+            .label_from_kv(&["component"])
+            .build()
+            .expect("No error during default drain creation")
+            .fuse();
+
+        let logger = slog::Logger::root(drain, o!());
+        info!(logger, "A info message");
+
+        let metrics = registry.gather();
+        for m in metrics {
+            if let "log_events" = m.get_name() {
+                let found = m.get_metric().iter().any(|metric| {
+                    metric
+                        .get_label()
+                        .iter()
+                        .any(|l| l.get_name() == "component" && l.get_value().is_empty())
+                });
+                assert!(found, "expected a log_events series with component=\"\"");
+            }
+        }
+    }
+
+    #[test]
+    fn kv_cardinality_limit_folds_overflow_values_to_empty_string() {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let drain = StoringDrain { records: &COUNTER };
+
+        let registry = Registry::new();
+        let drain = MonitoringDrainBuilder::new(drain)
+            .registry(&registry)
+            .label_from_kv(&["component"])
+            .kv_cardinality_limit(1)
+            .build()
+            .expect("No error during default drain creation")
+            .fuse();
+
+        let logger = slog::Logger::root(drain, o!());
+        info!(logger, "A info message"; "component" => "auth");
+        info!(logger, "A info message"; "component" => "billing");
+
+        let metrics = registry.gather();
+        for m in metrics {
+            if let "log_events" = m.get_name() {
+                let auth_series = m.get_metric().iter().find(|metric| {
+                    metric
+                        .get_label()
+                        .iter()
+                        .any(|l| l.get_name() == "component" && l.get_value() == "auth")
+                });
+                assert_eq!(
+                    1 as f64,
+                    auth_series.unwrap().get_counter().get_value(),
+                    "first value seen should keep its own series"
+                );
+
+                let overflow_series = m.get_metric().iter().find(|metric| {
+                    metric
+                        .get_label()
+                        .iter()
+                        .any(|l| l.get_name() == "component" && l.get_value().is_empty())
+                });
+                assert_eq!(
+                    1 as f64,
+                    overflow_series.unwrap().get_counter().get_value(),
+                    "value past the cardinality limit should fold into the \"\" bucket"
+                );
+
+                assert!(
+                    m.get_metric().iter().all(|metric| {
+                        !metric
+                            .get_label()
+                            .iter()
+                            .any(|l| l.get_name() == "component" && l.get_value() == "billing")
+                    }),
+                    "billing should not have gotten its own series"
+                );
+            }
+        }
     }
 }
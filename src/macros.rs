@@ -0,0 +1,19 @@
+//! Shared machinery for the `slog::Serializer` impls in [`crate::kv`] and
+//! [`crate::buffer`], which otherwise differ only in what they do with each
+//! stringified value.
+
+/// Generates `slog::Serializer` methods for the numeric/bool/char primitives
+/// that don't have a natural string representation already, stringifying
+/// each via `to_string` and handing it to `self.push`.
+macro_rules! emit_via_to_string {
+    ($($method:ident: $ty:ty),* $(,)?) => {
+        $(
+            fn $method(&mut self, key: slog::Key, val: $ty) -> slog::Result {
+                self.push(key, val.to_string());
+                Ok(())
+            }
+        )*
+    };
+}
+
+pub(crate) use emit_via_to_string;